@@ -1,38 +1,121 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    ffi::CString,
+    fs::{self, File},
+    io::{ErrorKind, Read, Write},
+    path::PathBuf,
+};
 
 use clap::Parser;
 
-use reedline::{DefaultPrompt, DefaultPromptSegment, FileBackedHistory, Reedline, Signal};
-use toy_debugger::process::{Pid, Process, ProcessState, StopReason};
+use nix::sys::signal::Signal as UnixSignal;
+use reedline::{DefaultPrompt, DefaultPromptSegment, ExternalPrinter, FileBackedHistory, Reedline, Signal};
+use toy_debugger::command::Command;
+use toy_debugger::event_loop::EventLoop;
+use toy_debugger::process::{LaunchOptions, Pid, Process, ProcessState, StopReason};
+use toy_debugger::script::{self, StepOutcome};
 
 #[derive(Parser)]
 struct Cli {
     #[arg(short, conflicts_with = "path")]
     pid: Option<i32>,
     path: Option<PathBuf>,
+    /// Arguments forwarded to the inferior, e.g. `td ./prog -- arg1 arg2`
+    #[arg(last = true)]
+    args: Vec<String>,
+    /// Redirect the inferior's stdout/stderr through pipes instead of
+    /// sharing the debugger's terminal
+    #[arg(long)]
+    capture_stdio: bool,
+    /// Run a single command non-interactively instead of the REPL
+    #[arg(long, conflicts_with = "script")]
+    eval: Option<String>,
+    /// Run commands from FILE (one per line) non-interactively instead of
+    /// the REPL
+    #[arg(long, conflicts_with = "eval")]
+    script: Option<PathBuf>,
 }
 
-fn handle_command(process: &mut Process, line: &str) -> Result<(), Box<dyn Error>> {
-    let mut args = line.split_whitespace();
-    let command = args.next().unwrap_or_default();
+/// Stdio handles pulled out of the `Process` before handing it to the
+/// `EventLoop`'s background thread, since they're independent of the
+/// ptrace/waitpid machinery that thread owns.
+struct Stdio {
+    stdin: Option<File>,
+    stdout: Option<File>,
+    stderr: Option<File>,
+}
+
+fn handle_command(event_loop: &EventLoop, stdio: &mut Stdio, line: &str) -> Result<(), Box<dyn Error>> {
+    let command: Command = match line.parse() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
 
-    if "continue".starts_with(command) {
-        process.resume()?;
-        let stop_reason = process.wait_on_signal()?;
-        print_stop_reason(process, &stop_reason);
-    } else {
-        eprintln!("Unknown command");
+    match command {
+        Command::Continue => event_loop.resume()?,
+        Command::Interrupt => event_loop.interrupt()?,
+        Command::Output => {
+            drain(stdio.stdout.as_mut(), "stdout");
+            drain(stdio.stderr.as_mut(), "stderr");
+        }
+        Command::Input(text) => feed_input(stdio.stdin.as_mut(), &text),
     }
 
     Ok(())
 }
 
-fn print_stop_reason(process: &Process, reason: &StopReason) {
+/// Writes a line to the inferior's captured stdin, if it was launched with
+/// `--capture-stdio`. A no-op (with a warning) otherwise, since there's no
+/// pipe to write to.
+fn feed_input(stream: Option<&mut File>, text: &str) {
+    let Some(stream) = stream else {
+        eprintln!("Inferior stdin isn't captured; pass --capture-stdio to use `input`");
+        return;
+    };
+
+    if let Err(e) = writeln!(stream, "{}", text) {
+        eprintln!("Error writing to inferior stdin: {}", e);
+    }
+}
+
+/// Reads and prints whatever is currently buffered on a captured stdio
+/// stream. The stream is non-blocking, so this returns as soon as it would
+/// otherwise block rather than waiting for more output.
+fn drain(stream: Option<&mut impl Read>, label: &str) {
+    let Some(stream) = stream else {
+        return;
+    };
+
+    let mut buf = [0u8; 4096];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => print!("{}", String::from_utf8_lossy(&buf[..n])),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => {
+                eprintln!("Error reading inferior {}: {}", label, e);
+                break;
+            }
+        }
+    }
+}
+
+fn print_stop_reason(pid: Pid, reason: &StopReason) {
     if let ProcessState::Running = reason.reason {
         return;
     }
 
-    println!("Process {} {}", process.pid(), reason);
+    println!("Process {} {}", pid, reason);
+}
+
+fn print_step_outcome(pid: Pid, outcome: StepOutcome) {
+    match outcome {
+        StepOutcome::Stopped(reason) => print_stop_reason(pid, &reason),
+        StepOutcome::Output(text) => print!("{}", text),
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -40,14 +123,49 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut process = match (cli.pid, cli.path) {
         (Some(pid), None) => Process::attach(Pid::from(pid))?,
-        (None, Some(path)) => Process::launch(&path)?,
+        (None, Some(path)) => {
+            let args = cli
+                .args
+                .into_iter()
+                .map(|arg| CString::new(arg.as_bytes()))
+                .collect::<Result<_, _>>()?;
+            let mut options = LaunchOptions::new().args(args);
+            if cli.capture_stdio {
+                options = options.capture_stdio();
+            }
+            Process::launch_with_options(&path, options)?
+        }
         _ => unreachable!(),
     };
 
+    if let Some(commands) = cli
+        .script
+        .map(|path| fs::read_to_string(path).map(|s| s.lines().map(str::to_string).collect::<Vec<_>>()))
+        .transpose()?
+        .or_else(|| cli.eval.map(|eval| vec![eval]))
+    {
+        let pid = process.pid();
+        for outcome in script::run_script(&mut process, commands.into_iter())? {
+            print_step_outcome(pid, outcome);
+        }
+        return Ok(());
+    }
+
+    let (stdin, stdout, stderr) = process.take_stdio();
+    let mut stdio = Stdio { stdin, stdout, stderr };
+
+    // Shared with the event loop's background thread so stop notifications
+    // for a `continue` in flight are printed as soon as they happen, even
+    // while `read_line` below is blocked waiting for the next command.
+    let printer = ExternalPrinter::default();
+    let event_loop = EventLoop::spawn(process, printer.clone());
+
     let history = Box::new(
         FileBackedHistory::with_file(8, "history.txt".into()).expect("Error configuring history"),
     );
-    let mut line_editor = Reedline::create().with_history(history);
+    let mut line_editor = Reedline::create()
+        .with_history(history)
+        .with_external_printer(printer);
     let prompt = DefaultPrompt::new(
         DefaultPromptSegment::Basic("td".to_string()),
         DefaultPromptSegment::Empty,
@@ -56,11 +174,16 @@ fn main() -> Result<(), Box<dyn Error>> {
         let signal = line_editor.read_line(&prompt);
         match signal {
             Ok(Signal::Success(buffer)) => {
-                handle_command(&mut process, &buffer).unwrap_or_else(|e| {
+                handle_command(&event_loop, &mut stdio, &buffer).unwrap_or_else(|e| {
                     eprintln!("Error handling command: {}", e);
                 });
             }
-            Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
+            Ok(Signal::CtrlC) => {
+                if let Err(e) = event_loop.signal(UnixSignal::SIGINT) {
+                    eprintln!("Error forwarding SIGINT to inferior: {}", e);
+                }
+            }
+            Ok(Signal::CtrlD) => {
                 break;
             }
             x => {
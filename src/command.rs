@@ -0,0 +1,49 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed REPL command, shared between the interactive loop and
+/// `script::run_script` so both dispatch off the same grammar instead of
+/// duplicating the prefix matching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Continue,
+    Interrupt,
+    Output,
+    /// Write a line to the inferior's stdin (only has an effect when
+    /// launched with `LaunchOptions::capture_stdio`).
+    Input(String),
+}
+
+#[derive(Debug)]
+pub struct UnknownCommand(pub String);
+
+impl fmt::Display for UnknownCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown command: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownCommand {}
+
+impl FromStr for Command {
+    type Err = UnknownCommand;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim_start();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        if command.is_empty() {
+            Err(UnknownCommand(command.to_string()))
+        } else if "continue".starts_with(command) {
+            Ok(Command::Continue)
+        } else if "stop".starts_with(command) || "interrupt".starts_with(command) {
+            Ok(Command::Interrupt)
+        } else if "output".starts_with(command) {
+            Ok(Command::Output)
+        } else if "input".starts_with(command) {
+            Ok(Command::Input(rest.trim_start().to_string()))
+        } else {
+            Err(UnknownCommand(command.to_string()))
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use nix::sys::signal::Signal;
+use reedline::ExternalPrinter;
+use thiserror::Error;
+
+use crate::process::{self, Pid, Process, ProcessState, StopReason};
+
+#[derive(Debug, Error)]
+pub enum EventLoopError {
+    #[error("Background event thread is no longer running")]
+    ThreadGone,
+}
+
+enum Command {
+    Continue,
+}
+
+/// Runs the inferior's `waitpid` loop on a background thread so the REPL
+/// stays responsive while it's running (the "helper thread" pattern).
+///
+/// The background thread owns the `Process` and only ever does two things:
+/// resume it on `Continue`, then block in `wait_on_signal` until it stops
+/// again, handing the resulting `StopReason` straight to reedline's
+/// `ExternalPrinter` so it surfaces even while the REPL is sitting idle in
+/// `read_line` (requires the caller to have installed the same printer
+/// with `Reedline::with_external_printer`). Signals that don't go through
+/// ptrace continue/stop (e.g. `SIGSTOP` to interrupt a running inferior, or
+/// a forwarded `SIGINT`) are sent straight to the pid with `kill(2)` from
+/// this struct's thread, since the background thread may be blocked in
+/// `waitpid` and wouldn't see a command in time.
+pub struct EventLoop {
+    pid: Pid,
+    commands: Option<Sender<Command>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EventLoop {
+    pub fn spawn(process: Process, printer: ExternalPrinter<String>) -> Self {
+        let pid = process.pid();
+        let (command_tx, command_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || Self::run(process, command_rx, printer));
+
+        Self {
+            pid,
+            commands: Some(command_tx),
+            handle: Some(handle),
+        }
+    }
+
+    fn run(mut process: Process, commands: Receiver<Command>, printer: ExternalPrinter<String>) {
+        let pid = process.pid();
+
+        for command in commands {
+            let Command::Continue = command;
+
+            let reason = process
+                .resume(process.last_signal())
+                .and_then(|_| process.wait_on_signal());
+
+            match reason {
+                Ok(reason) => {
+                    if let ProcessState::Running = reason.reason {
+                        continue;
+                    }
+                    let _ = printer.print(format!("Process {} {}", pid, reason));
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Resumes the inferior and returns immediately; the resulting
+    /// `StopReason` is printed by the background thread once it arrives.
+    pub fn resume(&self) -> Result<(), EventLoopError> {
+        self.commands
+            .as_ref()
+            .ok_or(EventLoopError::ThreadGone)?
+            .send(Command::Continue)
+            .map_err(|_| EventLoopError::ThreadGone)
+    }
+
+    /// Stops a running inferior by sending it `SIGSTOP` directly.
+    pub fn interrupt(&self) -> Result<(), process::ProcessError> {
+        process::send_signal(self.pid, Signal::SIGSTOP)
+    }
+
+    /// Forwards an arbitrary signal to the inferior directly.
+    pub fn signal(&self, signal: Signal) -> Result<(), process::ProcessError> {
+        process::send_signal(self.pid, signal)
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        // Drop the sender first so the background thread's `for command in
+        // commands` loop ends once it's between commands.
+        self.commands.take();
+
+        // If a `continue` is in flight, the thread is instead blocked in
+        // `wait_on_signal`'s `waitpid`, which dropping the sender can't
+        // unblock. Stop the inferior directly so that `waitpid` returns and
+        // the thread notices there are no more commands. Errors here mean
+        // the inferior is already gone, which is fine — the thread has
+        // already reaped it, so it isn't blocked in `waitpid` and leaves its
+        // loop once it sees the sender is gone.
+        let _ = process::send_signal(self.pid, Signal::SIGSTOP);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
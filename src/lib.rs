@@ -0,0 +1,4 @@
+pub mod command;
+pub mod event_loop;
+pub mod process;
+pub mod script;
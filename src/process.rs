@@ -1,15 +1,65 @@
-use std::ffi::CString;
+use std::ffi::{CString, OsString};
 use std::fmt::Display;
+use std::fs::File;
 use std::os::unix::ffi::OsStrExt;
-use std::path::Path;
+use std::os::unix::io::{FromRawFd, IntoRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::time::{Duration, Instant};
 
+use nix::errno::Errno;
+use nix::fcntl::{FcntlArg, OFlag, fcntl};
 use nix::sys::ptrace;
-use nix::sys::signal::kill;
-use nix::sys::wait::{WaitStatus, waitpid};
-use nix::unistd::{ForkResult, execvp, fork};
+use nix::sys::signal::{Signal, kill};
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::{ForkResult, chdir, close, dup2, execvp, execvpe, fork, pipe};
 use thiserror::Error;
 
+const STDIN_FILENO: i32 = 0;
+const STDOUT_FILENO: i32 = 1;
+const STDERR_FILENO: i32 = 2;
+
+/// Options controlling how a new inferior is spawned by
+/// [`Process::launch_with_options`].
+///
+/// `args` does not need to repeat the program path; `launch_with_options`
+/// prepends it automatically to form `argv[0]`.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub args: Vec<CString>,
+    pub env: Option<Vec<(OsString, OsString)>>,
+    pub cwd: Option<PathBuf>,
+    pub capture_stdio: bool,
+}
+
+impl LaunchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn args(mut self, args: Vec<CString>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn env(mut self, env: Vec<(OsString, OsString)>) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    pub fn cwd(mut self, cwd: PathBuf) -> Self {
+        self.cwd = Some(cwd);
+        self
+    }
+
+    /// Redirect the inferior's stdout/stderr (and stdin) through pipes
+    /// instead of sharing the debugger's terminal.
+    pub fn capture_stdio(mut self) -> Self {
+        self.capture_stdio = true;
+        self
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Pid(nix::unistd::Pid);
 
@@ -31,18 +81,60 @@ impl Display for Pid {
     }
 }
 
+/// Sends a signal to `pid` directly via `kill(2)`, independent of any
+/// ptrace stop/continue state. Exposed standalone (not just as
+/// `Process::send_signal`) so callers that only hold a `Pid` — e.g. the
+/// REPL thread while the `Process` itself is owned by a background event
+/// thread — can still deliver signals like `SIGSTOP`/`SIGINT`.
+pub fn send_signal(pid: Pid, signal: Signal) -> Result<(), ProcessError> {
+    kill(pid.0, signal).map_err(|_| ProcessError::Signal)
+}
+
+/// Signals that stop the inferior without being something a `resume`
+/// should ever re-inject.
+const NON_FORWARDABLE_SIGNALS: &[Signal] = &[
+    // Reported by ptrace for trace artifacts (the post-exec stop, syscall
+    // stops, breakpoints) that were never actually sent to the inferior;
+    // redelivering it would kill a program that has no handler for it
+    // instead of just continuing.
+    Signal::SIGTRAP,
+    // The debugger injects `SIGSTOP` itself to interrupt a running inferior
+    // (see `EventLoop::interrupt`); redelivering it or any of the other stop
+    // signals on `resume` would just re-stop the inferior instead of letting
+    // it run.
+    Signal::SIGSTOP,
+    Signal::SIGTSTP,
+    Signal::SIGTTIN,
+    Signal::SIGTTOU,
+];
+
+/// Picks out the signal from a stop that's safe to re-inject on the next
+/// `resume`.
+fn forwardable_signal(reason: &StopReason) -> Option<Signal> {
+    reason
+        .signal
+        .filter(|signal| !NON_FORWARDABLE_SIGNALS.contains(signal))
+}
+
 #[derive(Debug, Clone)]
 pub enum ProcessState {
     Stopped,
     Running,
     Exited,
     Terminated,
+    TimedOut,
+    /// Stopped at a ptrace event (e.g. `PTRACE_O_TRACEFORK`/`TRACEEXIT`); the
+    /// inner value is the raw `PTRACE_EVENT_*` code reported in the wait
+    /// status.
+    PtraceEvent(i32),
+    /// Stopped at a `PTRACE_O_TRACESYSGOOD` syscall-entry/exit stop.
+    PtraceSyscall,
 }
 
 pub struct StopReason {
     pub reason: ProcessState,
     pub exit_status: Option<i32>,
-    pub signal: Option<String>,
+    pub signal: Option<Signal>,
 }
 
 impl From<WaitStatus> for StopReason {
@@ -56,14 +148,33 @@ impl From<WaitStatus> for StopReason {
             WaitStatus::Signaled(_pid, signal, _core_dump) => StopReason {
                 reason: ProcessState::Terminated,
                 exit_status: None,
-                signal: Some(signal.to_string()),
+                signal: Some(signal),
             },
             WaitStatus::Stopped(_pid, signal) => StopReason {
                 reason: ProcessState::Stopped,
                 exit_status: None,
-                signal: Some(signal.to_string()),
+                signal: Some(signal),
+            },
+            WaitStatus::PtraceEvent(_pid, signal, event) => StopReason {
+                reason: ProcessState::PtraceEvent(event),
+                exit_status: None,
+                signal: Some(signal),
+            },
+            WaitStatus::PtraceSyscall(_pid) => StopReason {
+                reason: ProcessState::PtraceSyscall,
+                exit_status: None,
+                signal: None,
+            },
+            WaitStatus::Continued(_pid) => StopReason {
+                reason: ProcessState::Running,
+                exit_status: None,
+                signal: None,
+            },
+            WaitStatus::StillAlive => StopReason {
+                reason: ProcessState::Running,
+                exit_status: None,
+                signal: None,
             },
-            _ => todo!("Handle other wait statuses"),
         }
     }
 }
@@ -83,6 +194,9 @@ impl Display for StopReason {
                 write!(f, "stopped with signal: {}", self.signal.as_ref().unwrap())
             }
             ProcessState::Running => Ok(()),
+            ProcessState::TimedOut => write!(f, "timed out waiting for inferior"),
+            ProcessState::PtraceEvent(event) => write!(f, "stopped at ptrace event: {}", event),
+            ProcessState::PtraceSyscall => write!(f, "stopped at syscall"),
         }
     }
 }
@@ -103,6 +217,8 @@ pub enum ProcessError {
     Resume,
     #[error("Failed waiting for signal on inferior process")]
     Wait,
+    #[error("Failed to send signal to inferior process")]
+    Signal,
 }
 
 #[derive(Debug)]
@@ -110,16 +226,60 @@ pub struct Process {
     pid: Pid,
     terminate_on_end: bool,
     state: ProcessState,
+    stdin: Option<File>,
+    stdout: Option<File>,
+    stderr: Option<File>,
+    last_signal: Option<Signal>,
 }
 
 impl Process {
     pub fn launch(path: &Path) -> Result<Self, ProcessError> {
+        Self::launch_with_options(path, LaunchOptions::default())
+    }
+
+    pub fn launch_with_options(
+        path: &Path,
+        options: LaunchOptions,
+    ) -> Result<Self, ProcessError> {
+        let stdio_pipes = if options.capture_stdio {
+            let into_raw = |(read, write): (OwnedFd, OwnedFd)| (read.into_raw_fd(), write.into_raw_fd());
+            Some((
+                into_raw(pipe().map_err(|_| ProcessError::Exec)?),
+                into_raw(pipe().map_err(|_| ProcessError::Exec)?),
+                into_raw(pipe().map_err(|_| ProcessError::Exec)?),
+            ))
+        } else {
+            None
+        };
+
         match unsafe { fork().map_err(|_| ProcessError::Fork)? } {
             ForkResult::Parent { child } => {
+                let (stdin, stdout, stderr) = match stdio_pipes {
+                    Some(((stdin_read, stdin_write), (stdout_read, stdout_write), (stderr_read, stderr_write))) => {
+                        let _ = close(stdin_read);
+                        let _ = close(stdout_write);
+                        let _ = close(stderr_write);
+                        // Non-blocking so a REPL command can drain whatever is
+                        // currently buffered without hanging the prompt.
+                        let _ = fcntl(stdout_read, FcntlArg::F_SETFL(OFlag::O_NONBLOCK));
+                        let _ = fcntl(stderr_read, FcntlArg::F_SETFL(OFlag::O_NONBLOCK));
+                        (
+                            Some(unsafe { File::from_raw_fd(stdin_write) }),
+                            Some(unsafe { File::from_raw_fd(stdout_read) }),
+                            Some(unsafe { File::from_raw_fd(stderr_read) }),
+                        )
+                    }
+                    None => (None, None, None),
+                };
+
                 let mut process = Self {
                     pid: child.into(),
                     terminate_on_end: true,
                     state: ProcessState::Stopped,
+                    stdin,
+                    stdout,
+                    stderr,
+                    last_signal: None,
                 };
                 process.wait_on_signal()?;
 
@@ -127,10 +287,47 @@ impl Process {
             }
             ForkResult::Child => {
                 ptrace::traceme().map_err(|_| ProcessError::Traceme)?;
+
+                if let Some(((stdin_read, stdin_write), (stdout_read, stdout_write), (stderr_read, stderr_write))) =
+                    stdio_pipes
+                {
+                    let _ = close(stdin_write);
+                    let _ = close(stdout_read);
+                    let _ = close(stderr_read);
+                    dup2(stdin_read, STDIN_FILENO).map_err(|_| ProcessError::Exec)?;
+                    dup2(stdout_write, STDOUT_FILENO).map_err(|_| ProcessError::Exec)?;
+                    dup2(stderr_write, STDERR_FILENO).map_err(|_| ProcessError::Exec)?;
+                    let _ = close(stdin_read);
+                    let _ = close(stdout_write);
+                    let _ = close(stderr_write);
+                }
+
+                if let Some(cwd) = &options.cwd {
+                    chdir(cwd).map_err(|_| ProcessError::Exec)?;
+                }
+
                 let prog =
                     CString::new(path.as_os_str().as_bytes()).map_err(|_| ProcessError::Exec)?;
-                let args = [prog.clone()];
-                match execvp(&prog, &args) {
+                let mut args = vec![prog.clone()];
+                args.extend(options.args);
+
+                let result = match &options.env {
+                    Some(env) => {
+                        let env: Vec<CString> = env
+                            .iter()
+                            .map(|(key, value)| {
+                                let mut pair = key.as_bytes().to_vec();
+                                pair.push(b'=');
+                                pair.extend_from_slice(value.as_bytes());
+                                CString::new(pair).map_err(|_| ProcessError::Exec)
+                            })
+                            .collect::<Result<_, _>>()?;
+                        execvpe(&prog, &args, &env)
+                    }
+                    None => execvp(&prog, &args),
+                };
+
+                match result {
                     Ok(_) => unreachable!(),
                     Err(_) => {
                         eprintln!("Failed to exec process: {}", path.display());
@@ -150,30 +347,118 @@ impl Process {
             pid,
             terminate_on_end: false,
             state: ProcessState::Stopped,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            last_signal: None,
         };
         process.wait_on_signal()?;
 
         Ok(process)
     }
 
-    pub fn resume(&mut self) -> Result<(), ProcessError> {
-        ptrace::cont(self.pid.0, None).map_err(|_| ProcessError::Resume)?;
+    /// Resumes the inferior, optionally re-injecting a signal (e.g. the one
+    /// that caused the last stop) instead of swallowing it.
+    pub fn resume(&mut self, signal: Option<Signal>) -> Result<(), ProcessError> {
+        ptrace::cont(self.pid.0, signal).map_err(|_| ProcessError::Resume)?;
         self.state = ProcessState::Running;
 
         Ok(())
     }
 
+    /// Sends a signal to the inferior directly (e.g. to forward a Ctrl-C
+    /// from the debugger's terminal instead of exiting the debugger).
+    pub fn send_signal(&self, signal: Signal) -> Result<(), ProcessError> {
+        send_signal(self.pid, signal)
+    }
+
+    pub fn last_signal(&self) -> Option<Signal> {
+        self.last_signal
+    }
+
     pub fn wait_on_signal(&mut self) -> Result<StopReason, ProcessError> {
-        let wait_status = waitpid(self.pid.0, None).map_err(|_| ProcessError::Wait)?;
-        let reason: StopReason = wait_status.into();
-        self.state = reason.reason.clone();
+        self.wait_on_signal_timeout(None)
+    }
+
+    /// Waits for the inferior to change state, giving up after `timeout` has
+    /// elapsed instead of blocking forever.
+    ///
+    /// `timeout` of `None` behaves exactly like `wait_on_signal`. Otherwise
+    /// `waitpid` is polled with `WNOHANG` in a loop, sleeping a short
+    /// interval between polls, until the inferior changes state or the
+    /// budget runs out. On timeout, `self.state` is left untouched (the
+    /// inferior is still running) and `ProcessState::TimedOut` is returned.
+    pub fn wait_on_signal_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<StopReason, ProcessError> {
+        let Some(budget) = timeout else {
+            let wait_status = waitpid(self.pid.0, None).map_err(|_| ProcessError::Wait)?;
+            let reason: StopReason = wait_status.into();
+            self.state = reason.reason.clone();
+            self.last_signal = forwardable_signal(&reason);
 
-        Ok(reason)
+            return Ok(reason);
+        };
+
+        const POLL_INTERVAL: Duration = Duration::from_millis(2);
+        let deadline = Instant::now() + budget;
+
+        loop {
+            match waitpid(self.pid.0, Some(WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::StillAlive) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(StopReason {
+                            reason: ProcessState::TimedOut,
+                            exit_status: None,
+                            signal: None,
+                        });
+                    }
+                    std::thread::sleep(POLL_INTERVAL.min(remaining));
+                }
+                Ok(wait_status) => {
+                    let reason: StopReason = wait_status.into();
+                    self.state = reason.reason.clone();
+                    self.last_signal = forwardable_signal(&reason);
+
+                    return Ok(reason);
+                }
+                Err(Errno::EINTR) => continue,
+                Err(_) => return Err(ProcessError::Wait),
+            }
+        }
     }
 
     pub fn pid(&self) -> Pid {
         self.pid
     }
+
+    /// Takes ownership of the captured stdio handles, if any, leaving the
+    /// `Process` without them. Used to hand the streams to a caller that
+    /// keeps them on a different thread than the one driving `waitpid`
+    /// (see `event_loop::EventLoop`).
+    pub fn take_stdio(&mut self) -> (Option<File>, Option<File>, Option<File>) {
+        (self.stdin.take(), self.stdout.take(), self.stderr.take())
+    }
+
+    /// The write end of the inferior's stdin, if launched with
+    /// [`LaunchOptions::capture_stdio`].
+    pub fn stdin(&mut self) -> Option<&mut File> {
+        self.stdin.as_mut()
+    }
+
+    /// The read end of the inferior's stdout, if launched with
+    /// [`LaunchOptions::capture_stdio`].
+    pub fn stdout(&mut self) -> Option<&mut File> {
+        self.stdout.as_mut()
+    }
+
+    /// The read end of the inferior's stderr, if launched with
+    /// [`LaunchOptions::capture_stdio`].
+    pub fn stderr(&mut self) -> Option<&mut File> {
+        self.stderr.as_mut()
+    }
 }
 
 #[allow(unused_must_use)]
@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Write};
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::command::{Command, UnknownCommand};
+use crate::process::{Process, ProcessError, ProcessState, StopReason};
+
+/// How long a scripted `continue`/`interrupt` waits for the inferior to
+/// stop before giving up. Unlike the interactive REPL — where a human can
+/// always `Ctrl-D` out of a wedged session — a script has no one watching,
+/// so it must bound the wait itself instead of blocking forever.
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error(transparent)]
+    Process(#[from] ProcessError),
+    #[error(transparent)]
+    UnknownCommand(#[from] UnknownCommand),
+    #[error("Failed to write to inferior stdin: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Result of running a single scripted command.
+pub enum StepOutcome {
+    /// The inferior changed state.
+    Stopped(StopReason),
+    /// Captured stdio that was drained, if any was pending.
+    Output(String),
+}
+
+fn drain_to_string(stream: Option<&mut File>, out: &mut String) {
+    let Some(stream) = stream else {
+        return;
+    };
+
+    let mut buf = [0u8; 4096];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => out.push_str(&String::from_utf8_lossy(&buf[..n])),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => break,
+        }
+    }
+}
+
+fn step(process: &mut Process, line: &str) -> Result<StepOutcome, ScriptError> {
+    match line.parse()? {
+        Command::Continue => {
+            process.resume(process.last_signal())?;
+            Ok(StepOutcome::Stopped(
+                process.wait_on_signal_timeout(Some(STEP_TIMEOUT))?,
+            ))
+        }
+        Command::Interrupt => {
+            process.send_signal(nix::sys::signal::Signal::SIGSTOP)?;
+            Ok(StepOutcome::Stopped(
+                process.wait_on_signal_timeout(Some(STEP_TIMEOUT))?,
+            ))
+        }
+        Command::Output => {
+            let mut output = String::new();
+            drain_to_string(process.stdout(), &mut output);
+            drain_to_string(process.stderr(), &mut output);
+            Ok(StepOutcome::Output(output))
+        }
+        Command::Input(text) => {
+            if let Some(stdin) = process.stdin() {
+                writeln!(stdin, "{}", text)?;
+            }
+            Ok(StepOutcome::Output(String::new()))
+        }
+    }
+}
+
+/// Runs `commands` against `process` non-interactively — e.g. from a
+/// `--eval` argument or a script file — instead of the reedline loop,
+/// stopping at the first error, once the inferior exits or is terminated,
+/// or once a `continue`/`interrupt` times out waiting for it to stop (see
+/// `STEP_TIMEOUT`). Unless a command failed, returns every outcome produced
+/// up to that point, so a caller can inspect or print the whole run; on an
+/// error only the error is returned and the earlier outcomes are dropped.
+/// Useful for reproducible debugging sessions and integration tests.
+pub fn run_script(
+    process: &mut Process,
+    commands: impl Iterator<Item = String>,
+) -> Result<Vec<StepOutcome>, ScriptError> {
+    let mut outcomes = Vec::new();
+
+    for line in commands {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let outcome = step(process, &line)?;
+        let stop_script = matches!(
+            outcome,
+            StepOutcome::Stopped(StopReason {
+                reason: ProcessState::Exited | ProcessState::Terminated | ProcessState::TimedOut,
+                ..
+            })
+        );
+        outcomes.push(outcome);
+        if stop_script {
+            break;
+        }
+    }
+
+    Ok(outcomes)
+}